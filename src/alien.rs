@@ -1,144 +1,173 @@
-use crate::bullet::{Bullet, BulletFactory};
-use ggez::graphics::{DrawParam, Drawable};
+use ggez::graphics;
+use ggez::graphics::{Canvas, DrawParam, Drawable};
 use ggez::mint::Point2;
-use ggez::{graphics, Context, GameResult};
-use rand::Rng;
 use std::fmt;
 use std::fmt::{Debug, Formatter};
 use std::rc::Rc;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
-#[derive(Debug, Copy, Clone)]
-struct MovementPlan {
-    start_pos: (f32, f32),
-    next_pos: (f32, f32),
-    plan_made: Instant,
-    duration: Duration,
-}
-
-#[derive(Debug, Copy, Clone)]
-struct FiringPlan {
-    dangerous: bool,
-    plan_made: Instant,
-    delay: Duration,
+/// What an alien is doing right now, independent of the fleet's shared
+/// march. Only the alien the fleet has picked to shoot next ever moves to
+/// `Aim` or `Fire`; any alien can drop into `Evade` when a bullet comes close.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AlienState {
+    /// Holding its assigned slot in the formation.
+    Patrol,
+    /// Nudging sideways to line up with the player before firing.
+    Aim,
+    /// Lined up with the player and clear to fire.
+    Fire,
+    /// Sliding away from a bullet that's come too close.
+    Evade,
 }
 
+/// A single alien in a fleet.
+///
+/// Its screen position is `anchor + home_offset + ai_offset`: `home_offset`
+/// is its fixed slot in the grid, `anchor` is the fleet's shared march
+/// position (see `AlienFleet`), and `ai_offset` is a small perturbation
+/// driven by this alien's own `AlienState` machine - aiming at the player
+/// before firing, or evading an incoming bullet.
 pub struct Alien {
-    pos: (f32, f32),
-    x_movement_range: (f32, f32),
+    home_offset: (f32, f32),
+    ai_offset: (f32, f32),
+    alive: bool,
     idle: Rc<graphics::Image>,
     firing: Rc<graphics::Image>,
-    firing_plan: Option<FiringPlan>,
-    movement_plan: Option<MovementPlan>,
+    state: AlienState,
 }
 
 impl Debug for Alien {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "Alien {{ pos: {:?}, ... }}", self.pos)
+        write!(
+            f,
+            "Alien {{ home_offset: {:?}, alive: {:?}, state: {:?}, ... }}",
+            self.home_offset, self.alive, self.state
+        )
     }
 }
 
 impl Alien {
-    pub fn pos(&self) -> (f32, f32) {
-        self.pos
-    }
+    /// How fast an aiming alien nudges toward the player's x, in pixels per second.
+    const AIM_SPEED: f32 = 60.0;
+    /// How fast an evading alien slides away from a threatening bullet.
+    const EVADE_SPEED: f32 = 90.0;
+    /// How close a bullet's x has to be to the alien's to trigger `Evade`.
+    const EVADE_TRIGGER_DISTANCE: f32 = 24.0;
+    /// How close to the player's x counts as "lined up" and ready to fire.
+    const AIM_ALIGN_TOLERANCE: f32 = 6.0;
+    /// How far an alien is allowed to stray from its formation slot.
+    const MAX_AI_OFFSET: f32 = 40.0;
 
-    pub fn starting_at(
-        pos: (f32, f32),
-        x_movement_range: (f32, f32),
-        idle: Rc<graphics::Image>,
-        firing: Rc<graphics::Image>,
-    ) -> Alien {
+    pub fn new(home_offset: (f32, f32), idle: Rc<graphics::Image>, firing: Rc<graphics::Image>) -> Alien {
         Alien {
-            pos,
-            x_movement_range,
+            home_offset,
+            ai_offset: (0.0, 0.0),
+            alive: true,
             idle,
             firing,
-            movement_plan: None,
-            firing_plan: None,
+            state: AlienState::Patrol,
         }
     }
 
-    fn will_fire(&self, now: Instant) -> bool {
-        if let Some(plan) = self.firing_plan {
-            plan.plan_made + plan.delay < now
-        } else {
-            false
-        }
+    pub fn home_offset(&self) -> (f32, f32) {
+        self.home_offset
+    }
+
+    /// The alien's own `Aim`/`Evade` perturbation away from `home_offset`.
+    pub fn ai_offset(&self) -> (f32, f32) {
+        self.ai_offset
+    }
+
+    pub fn pos(&self, anchor: (f32, f32)) -> (f32, f32) {
+        (
+            anchor.0 + self.home_offset.0 + self.ai_offset.0,
+            anchor.1 + self.home_offset.1 + self.ai_offset.1,
+        )
+    }
+
+    pub fn alive(&self) -> bool {
+        self.alive
     }
 
-    pub fn update(
+    pub fn kill(&mut self) {
+        self.alive = false;
+    }
+
+    pub fn state(&self) -> AlienState {
+        self.state
+    }
+
+    /// Chooses this alien's next activity. `wants_to_fire` is set by the
+    /// fleet for whichever alien it has picked to shoot next.
+    pub fn plan(
         &mut self,
-        now: Instant,
-        bullet_factory: &mut impl BulletFactory,
-    ) -> Option<Bullet> {
-        let mut rng = rand::thread_rng();
+        anchor: (f32, f32),
+        player_pos: (f32, f32),
+        nearby_bullet_xs: &[f32],
+        wants_to_fire: bool,
+    ) {
+        let pos = self.pos(anchor);
+        let threatened = nearby_bullet_xs
+            .iter()
+            .any(|&x| (x - pos.0).abs() < Alien::EVADE_TRIGGER_DISTANCE);
 
-        let (min_x, max_x) = self.x_movement_range;
-        let start_pos = self.pos;
-        let mut gen_movement_plan = || MovementPlan {
-            start_pos,
-            next_pos: (rng.gen_range(min_x, max_x), start_pos.1),
-            plan_made: now,
-            duration: Duration::from_millis(rand::thread_rng().gen_range(300, 2000)),
+        self.state = if threatened {
+            AlienState::Evade
+        } else if wants_to_fire {
+            if (player_pos.0 - pos.0).abs() <= Alien::AIM_ALIGN_TOLERANCE {
+                AlienState::Fire
+            } else {
+                AlienState::Aim
+            }
+        } else {
+            AlienState::Patrol
         };
+    }
 
-        match &self.movement_plan {
-            &None => self.movement_plan = Some(gen_movement_plan()),
-            Some(plan) => {
-                if plan.plan_made + plan.duration < now {
-                    self.pos = plan.next_pos;
-                    self.movement_plan = Some(gen_movement_plan())
-                } else {
-                    let tween_pct =
-                        (now - plan.plan_made).as_secs_f32() / plan.duration.as_secs_f32();
-                    self.pos = (
-                        plan.start_pos.0 + tween_pct * (plan.next_pos.0 - plan.start_pos.0),
-                        plan.start_pos.1 + tween_pct * (plan.next_pos.1 - plan.start_pos.1),
-                    )
-                }
+    /// Executes whatever the current state calls for, nudging `ai_offset`.
+    pub fn step(&mut self, dt: Duration, anchor: (f32, f32), player_pos: (f32, f32), nearby_bullet_xs: &[f32]) {
+        match self.state {
+            AlienState::Patrol | AlienState::Fire => {
+                let step = (Alien::AIM_SPEED * dt.as_secs_f32()).min(self.ai_offset.0.abs());
+                self.ai_offset.0 -= self.ai_offset.0.signum() * step;
             }
-        }
-
-        let mut fired = None;
-        match self.firing_plan {
-            None => {
-                self.firing_plan = Some(FiringPlan {
-                    dangerous: false,
-                    plan_made: now,
-                    delay: Duration::from_millis(1000),
-                })
+            AlienState::Aim => {
+                let pos = self.pos(anchor);
+                let max_step = Alien::AIM_SPEED * dt.as_secs_f32();
+                let dx = (player_pos.0 - pos.0).clamp(-max_step, max_step);
+                self.ai_offset.0 = (self.ai_offset.0 + dx).clamp(-Alien::MAX_AI_OFFSET, Alien::MAX_AI_OFFSET);
             }
-            Some(plan) => {
-                if plan.plan_made + plan.delay < now {
-                    if plan.dangerous {
-                        fired = Some(bullet_factory.red_bullet(self.pos));
-                    } else {
-                        fired = Some(bullet_factory.green_bullet(self.pos));
-                    }
-                    self.firing_plan = Some(FiringPlan {
-                        dangerous: rand::thread_rng().gen_bool(0.2),
-                        plan_made: now,
-                        delay: Duration::from_millis(rand::thread_rng().gen_range(200, 700)),
-                    })
+            AlienState::Evade => {
+                let pos = self.pos(anchor);
+                let threat_x = nearby_bullet_xs.iter().copied().min_by(|a, b| {
+                    (*a - pos.0).abs().partial_cmp(&(*b - pos.0).abs()).unwrap()
+                });
+                if let Some(threat_x) = threat_x {
+                    let direction = if threat_x >= pos.0 { -1.0 } else { 1.0 };
+                    let max_step = Alien::EVADE_SPEED * dt.as_secs_f32();
+                    self.ai_offset.0 =
+                        (self.ai_offset.0 + direction * max_step).clamp(-Alien::MAX_AI_OFFSET, Alien::MAX_AI_OFFSET);
                 }
             }
         }
-
-        fired
     }
 
-    pub fn draw(&self, ctx: &mut Context) -> GameResult<()> {
-        let sprite = if self.will_fire(Instant::now() + Duration::from_millis(200)) {
+    pub fn draw(&self, canvas: &mut Canvas, anchor: (f32, f32)) {
+        if !self.alive {
+            return;
+        }
+        let pos = self.pos(anchor);
+        let sprite = if self.state == AlienState::Fire {
             &self.firing
         } else {
             &self.idle
         };
         sprite.draw(
-            ctx,
+            canvas,
             DrawParam::default()
-                .offset(Point2{x: 0.5, y: 0.5})
-                .dest(Point2{x: self.pos.0, y: self.pos.1}),
+                .offset(Point2 { x: 0.5, y: 0.5 })
+                .dest(Point2 { x: pos.0, y: pos.1 }),
         )
     }
 }
@@ -32,17 +32,12 @@ impl Debug for Bullet {
 }
 
 impl Bullet {
-    const VELOCITY: f32 = 500.0;
-
     pub fn pos(&self) -> (f32, f32) {
         self.pos
     }
 
-    pub fn move_down(&mut self, time_passed: Duration) {
-        self.pos = (
-            self.pos.0,
-            self.pos.1 + Bullet::VELOCITY * time_passed.as_secs_f32(),
-        )
+    pub fn move_down(&mut self, time_passed: Duration, velocity: f32) {
+        self.pos = (self.pos.0, self.pos.1 + velocity * time_passed.as_secs_f32())
     }
 
     pub fn deadly(&self) -> bool {
@@ -0,0 +1,67 @@
+use ggez::Context;
+use serde::Deserialize;
+use std::io::Read;
+
+/// Gameplay tuning values loaded from `resources/balance.toml`, so
+/// rebalancing the game doesn't require a recompile. Any field missing from
+/// the file falls back to the default below.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct GameConfig {
+    pub player_speed: f32,
+    pub bullet_velocity: f32,
+    pub alien_base_speed: f32,
+    pub alien_row_drop: f32,
+    pub alien_first_fire_delay_ms: u64,
+    pub alien_fire_delay_min_ms: u64,
+    pub alien_fire_delay_max_ms: u64,
+    pub dangerous_bullet_chance: f64,
+    pub player_start: (f32, f32),
+    pub alien_fleet_start: (f32, f32),
+}
+
+impl Default for GameConfig {
+    fn default() -> GameConfig {
+        GameConfig {
+            player_speed: 1000.0,
+            bullet_velocity: 500.0,
+            alien_base_speed: 30.0,
+            alien_row_drop: 20.0,
+            alien_first_fire_delay_ms: 1000,
+            alien_fire_delay_min_ms: 200,
+            alien_fire_delay_max_ms: 700,
+            dangerous_bullet_chance: 0.2,
+            player_start: (50.0, 550.0),
+            alien_fleet_start: (80.0, 50.0),
+        }
+    }
+}
+
+impl GameConfig {
+    /// Loads `resources/balance.toml` off of `ctx`'s resource path, falling
+    /// back to `GameConfig::default()` if the file is missing or malformed.
+    pub fn load(ctx: &mut Context) -> GameConfig {
+        ggez::filesystem::open(ctx, "/balance.toml")
+            .ok()
+            .and_then(|mut file| {
+                let mut contents = String::new();
+                file.read_to_string(&mut contents).ok()?;
+                toml::from_str(&contents).ok()
+            })
+            .unwrap_or_default()
+            .sanitized()
+    }
+
+    /// Clamps values a hand-edited `balance.toml` could otherwise set to
+    /// something that parses fine but panics later - e.g. `rand::gen_range`
+    /// requires `min < max`, and `gen_bool` requires its argument to be in
+    /// `0.0..=1.0`. A bad edit should degrade the balance, not crash the
+    /// first time the fleet rolls over its firing plan.
+    fn sanitized(mut self) -> GameConfig {
+        if self.alien_fire_delay_max_ms <= self.alien_fire_delay_min_ms {
+            self.alien_fire_delay_max_ms = self.alien_fire_delay_min_ms.saturating_add(1);
+        }
+        self.dangerous_bullet_chance = self.dangerous_bullet_chance.clamp(0.0, 1.0);
+        self
+    }
+}
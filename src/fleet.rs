@@ -0,0 +1,247 @@
+use crate::alien::{Alien, AlienState};
+use crate::bullet::{Bullet, BulletFactory};
+use crate::config::GameConfig;
+use ggez::graphics;
+use ggez::graphics::Canvas;
+use rand::Rng;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Copy, Clone)]
+struct FiringPlan {
+    shooter: usize,
+    dangerous: bool,
+    plan_made: Instant,
+    delay: Duration,
+}
+
+/// A grid of aliens that marches as a single body, the way the classic
+/// Space Invaders fleet does: the whole formation steps sideways until its
+/// bounding box would cross `x_movement_range`, at which point every alien
+/// drops down a row and the formation reverses direction. As aliens are
+/// destroyed the bounding box shrinks to hug the survivors and the march
+/// speeds up.
+///
+/// The march only moves a shared `anchor` - each alien's own position is
+/// `anchor + home_offset`, plus whatever small perturbation its own
+/// `AlienState` machine is applying (see `alien::Alien`) to aim at the
+/// player or evade a bullet.
+pub struct AlienFleet {
+    aliens: Vec<Alien>,
+    rows: usize,
+    cols: usize,
+    x_movement_range: (f32, f32),
+    anchor: (f32, f32),
+    direction: f32,
+    firing_plan: Option<FiringPlan>,
+    config: Rc<GameConfig>,
+}
+
+impl AlienFleet {
+    /// The fastest the fleet is allowed to speed up to as aliens die.
+    const MAX_SPEED_SCALE: f32 = 6.0;
+
+    pub fn starting_at(
+        top_left: (f32, f32),
+        spacing: (f32, f32),
+        rows: usize,
+        cols: usize,
+        x_movement_range: (f32, f32),
+        idle: Rc<graphics::Image>,
+        firing: Rc<graphics::Image>,
+        config: Rc<GameConfig>,
+    ) -> AlienFleet {
+        let mut aliens = Vec::with_capacity(rows * cols);
+        for row in 0..rows {
+            for col in 0..cols {
+                let home_offset = (col as f32 * spacing.0, row as f32 * spacing.1);
+                aliens.push(Alien::new(home_offset, idle.clone(), firing.clone()));
+            }
+        }
+        AlienFleet {
+            aliens,
+            rows,
+            cols,
+            x_movement_range,
+            anchor: top_left,
+            direction: 1.0,
+            firing_plan: None,
+            config,
+        }
+    }
+
+    pub fn aliens(&self) -> &[Alien] {
+        &self.aliens
+    }
+
+    pub fn anchor(&self) -> (f32, f32) {
+        self.anchor
+    }
+
+    pub fn alive_count(&self) -> usize {
+        self.aliens.iter().filter(|alien| alien.alive()).count()
+    }
+
+    /// The horizontal span the surviving aliens actually occupy, relative to
+    /// the anchor - their formation slot plus whatever `ai_offset` their own
+    /// `Aim`/`Evade` state has nudged them by, so a straying alien still
+    /// counts toward the edge the fleet reverses at.
+    fn formation_span(&self) -> Option<(f32, f32)> {
+        let mut min_x = f32::INFINITY;
+        let mut max_x = f32::NEG_INFINITY;
+        for alien in self.aliens.iter().filter(|alien| alien.alive()) {
+            let x = alien.home_offset().0 + alien.ai_offset().0;
+            min_x = min_x.min(x);
+            max_x = max_x.max(x);
+        }
+        if min_x.is_finite() {
+            Some((min_x, max_x))
+        } else {
+            None
+        }
+    }
+
+    /// The index of the frontmost surviving alien in each column - the ones
+    /// with a clear line of fire down at the player.
+    fn bottom_row_indices(&self) -> Vec<usize> {
+        (0..self.cols)
+            .filter_map(|col| {
+                (0..self.rows)
+                    .rev()
+                    .map(|row| row * self.cols + col)
+                    .find(|&idx| self.aliens[idx].alive())
+            })
+            .collect()
+    }
+
+    pub fn update(
+        &mut self,
+        now: Instant,
+        dt: Duration,
+        player_pos: (f32, f32),
+        nearby_bullets: &[Bullet],
+        bullet_factory: &mut impl BulletFactory,
+    ) -> Option<Bullet> {
+        self.march(dt);
+        self.update_ai(now, dt, player_pos, nearby_bullets);
+        self.fire(now, bullet_factory)
+    }
+
+    fn march(&mut self, dt: Duration) {
+        let (min_offset, max_offset) = match self.formation_span() {
+            Some(span) => span,
+            None => return,
+        };
+
+        let total = (self.rows * self.cols) as f32;
+        let alive = self.alive_count().max(1) as f32;
+        let speed_scale = (total / alive).min(AlienFleet::MAX_SPEED_SCALE);
+        let dx = self.direction * self.config.alien_base_speed * speed_scale * dt.as_secs_f32();
+
+        let (range_min, range_max) = self.x_movement_range;
+        let (min_x, max_x) = (self.anchor.0 + min_offset, self.anchor.0 + max_offset);
+        if min_x + dx < range_min || max_x + dx > range_max {
+            self.direction = -self.direction;
+            self.anchor.1 += self.config.alien_row_drop;
+        } else {
+            self.anchor.0 += dx;
+        }
+    }
+
+    /// Lets every surviving alien plan and act on its own `AlienState` -
+    /// aiming at the player if it's the chosen shooter, or evading a bullet
+    /// that's come too close. `nearby_bullets` is filtered per-alien to
+    /// bullets above it, since only those can be threatening it.
+    fn update_ai(&mut self, now: Instant, dt: Duration, player_pos: (f32, f32), nearby_bullets: &[Bullet]) {
+        let anchor = self.anchor;
+        let wants_to_fire = self.firing_plan.and_then(|plan| {
+            if plan.plan_made + plan.delay <= now {
+                Some(plan.shooter)
+            } else {
+                None
+            }
+        });
+
+        for (idx, alien) in self.aliens.iter_mut().enumerate() {
+            if !alien.alive() {
+                continue;
+            }
+            let pos = alien.pos(anchor);
+            let incoming_xs: Vec<f32> = nearby_bullets
+                .iter()
+                .filter(|bullet| bullet.pos().1 < pos.1)
+                .map(|bullet| bullet.pos().0)
+                .collect();
+
+            alien.plan(anchor, player_pos, &incoming_xs, wants_to_fire == Some(idx));
+            alien.step(dt, anchor, player_pos, &incoming_xs);
+        }
+    }
+
+    fn fire(&mut self, now: Instant, bullet_factory: &mut impl BulletFactory) -> Option<Bullet> {
+        let mut rng = rand::thread_rng();
+
+        let plan = match self.firing_plan {
+            None => {
+                let candidates = self.bottom_row_indices();
+                if !candidates.is_empty() {
+                    let shooter = candidates[rng.gen_range(0, candidates.len())];
+                    self.firing_plan = Some(FiringPlan {
+                        shooter,
+                        dangerous: false,
+                        plan_made: now,
+                        delay: Duration::from_millis(self.config.alien_first_fire_delay_ms),
+                    });
+                }
+                return None;
+            }
+            Some(plan) => plan,
+        };
+
+        if plan.plan_made + plan.delay >= now {
+            return None;
+        }
+
+        if !self.aliens[plan.shooter].alive() {
+            // The shooter died before lining up a shot - drop the plan and
+            // pick a fresh one next tick.
+            self.firing_plan = None;
+            return None;
+        }
+
+        if self.aliens[plan.shooter].state() != AlienState::Fire {
+            // Still aiming; wait for it to line up with the player.
+            return None;
+        }
+
+        let pos = self.aliens[plan.shooter].pos(self.anchor);
+        let fired = Some(if plan.dangerous {
+            bullet_factory.red_bullet(pos)
+        } else {
+            bullet_factory.green_bullet(pos)
+        });
+
+        let candidates = self.bottom_row_indices();
+        let next_shooter = if candidates.is_empty() {
+            plan.shooter
+        } else {
+            candidates[rng.gen_range(0, candidates.len())]
+        };
+        self.firing_plan = Some(FiringPlan {
+            shooter: next_shooter,
+            dangerous: rng.gen_bool(self.config.dangerous_bullet_chance),
+            plan_made: now,
+            delay: Duration::from_millis(
+                rng.gen_range(self.config.alien_fire_delay_min_ms, self.config.alien_fire_delay_max_ms),
+            ),
+        });
+
+        fired
+    }
+
+    pub fn draw(&self, canvas: &mut Canvas) {
+        for alien in self.aliens.iter() {
+            alien.draw(canvas, self.anchor);
+        }
+    }
+}
@@ -0,0 +1,547 @@
+use crate::bullet::{Bullet, BulletFactoryImpl};
+use crate::config::GameConfig;
+use crate::fleet::AlienFleet;
+use crate::shield::Bunker;
+use crate::simple_collision::{are_colliding, CollisionRect, SpatialGrid};
+use crate::{AudioData, SpriteData};
+use ggez::audio::SoundSource;
+use ggez::graphics::{Canvas, Color, DrawMode, DrawParam, Drawable, Mesh, Text};
+use ggez::input::keyboard::KeyCode;
+use ggez::mint::Point2;
+use ggez::{graphics, Context, GameResult};
+use rand::Rng;
+use std::collections::HashSet;
+use std::f32::consts::FRAC_PI_2;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+const GAME_OVER_MESSAGES: [&str; 13] = [
+    "You lost",
+    "You can do better than that",
+    "Catch Them!",
+    "Better luck next time",
+    "Mwahahahaha",
+    "Be better than that",
+    "Good job...?",
+    "Failed You Have",
+    "Nice try",
+    "Get a different hobby",
+    "You missed a spot",
+    "Great job",
+    "Wax on. Wax off.",
+];
+
+/// Resources that are expensive to load and don't change across scene
+/// transitions, so scenes borrow them instead of reloading images/audio.
+pub struct SharedState {
+    pub sprites: SpriteData,
+    pub audio: AudioData,
+}
+
+impl SharedState {
+    pub fn load(ctx: &mut Context) -> GameResult<SharedState> {
+        Ok(SharedState {
+            sprites: SpriteData::load_from_resources(ctx)?,
+            audio: AudioData::load_from_resources(ctx)?,
+        })
+    }
+}
+
+/// One state of the game's overall flow (title screen, active play, game
+/// over, ...). `EventHandler` delegates every event to whichever scene is
+/// currently active.
+pub trait Scene {
+    fn update(&mut self, ctx: &mut Context, shared: &mut SharedState) -> GameResult<()>;
+    fn draw(&mut self, ctx: &mut Context, shared: &SharedState, canvas: &mut Canvas) -> GameResult<()>;
+    fn key_down(&mut self, ctx: &mut Context, keycode: KeyCode);
+    fn key_up(&mut self, _ctx: &mut Context, _keycode: KeyCode) {}
+    fn resize(&mut self, _width: f32, _height: f32) {}
+    /// If this scene is ready to hand control to another one, returns it.
+    fn next_scene(&mut self, ctx: &mut Context, shared: &SharedState) -> Option<Box<dyn Scene>>;
+}
+
+pub struct TitleScene {
+    start: bool,
+}
+
+impl TitleScene {
+    pub fn new() -> TitleScene {
+        TitleScene { start: false }
+    }
+}
+
+impl Scene for TitleScene {
+    fn update(&mut self, _ctx: &mut Context, _shared: &mut SharedState) -> GameResult<()> {
+        Ok(())
+    }
+
+    fn draw(&mut self, _ctx: &mut Context, shared: &SharedState, canvas: &mut Canvas) -> GameResult<()> {
+        shared.sprites.background.draw(canvas, DrawParam::default());
+        let screen = canvas.screen_coordinates().unwrap();
+        Text::new("ALIEN GAME").draw(
+            canvas,
+            DrawParam::default().dest(Point2 {
+                x: screen.w / 2.0 - 80.0,
+                y: screen.h / 2.0 - 40.0,
+            }),
+        );
+        Text::new("Press Enter to start").draw(
+            canvas,
+            DrawParam::default().dest(Point2 {
+                x: screen.w / 2.0 - 80.0,
+                y: screen.h / 2.0,
+            }),
+        );
+        Ok(())
+    }
+
+    fn key_down(&mut self, ctx: &mut Context, keycode: KeyCode) {
+        match keycode {
+            KeyCode::Return => self.start = true,
+            KeyCode::Escape => ctx.request_quit(),
+            _ => (),
+        }
+    }
+
+    fn next_scene(&mut self, ctx: &mut Context, shared: &SharedState) -> Option<Box<dyn Scene>> {
+        if self.start {
+            Some(Box::new(GameScene::starting(ctx, shared)))
+        } else {
+            None
+        }
+    }
+}
+
+pub struct GameOverScene {
+    message: &'static str,
+    score: u32,
+    want_title: bool,
+}
+
+impl GameOverScene {
+    pub fn new(score: u32) -> GameOverScene {
+        let message = GAME_OVER_MESSAGES[rand::thread_rng().gen_range(0, GAME_OVER_MESSAGES.len())];
+        GameOverScene {
+            message,
+            score,
+            want_title: false,
+        }
+    }
+}
+
+impl Scene for GameOverScene {
+    fn update(&mut self, _ctx: &mut Context, _shared: &mut SharedState) -> GameResult<()> {
+        Ok(())
+    }
+
+    fn draw(&mut self, _ctx: &mut Context, shared: &SharedState, canvas: &mut Canvas) -> GameResult<()> {
+        shared.sprites.background.draw(canvas, DrawParam::default());
+        let screen = canvas.screen_coordinates().unwrap();
+        Text::new(self.message).draw(
+            canvas,
+            DrawParam::default().dest(Point2 {
+                x: screen.w / 2.0 - 80.0,
+                y: screen.h / 2.0 - 40.0,
+            }),
+        );
+        Text::new(format!("Final score: {}", self.score)).draw(
+            canvas,
+            DrawParam::default().dest(Point2 {
+                x: screen.w / 2.0 - 80.0,
+                y: screen.h / 2.0,
+            }),
+        );
+        Ok(())
+    }
+
+    fn key_down(&mut self, ctx: &mut Context, keycode: KeyCode) {
+        match keycode {
+            KeyCode::Return => self.want_title = true,
+            KeyCode::Escape => ctx.request_quit(),
+            _ => (),
+        }
+    }
+
+    fn next_scene(&mut self, _ctx: &mut Context, _shared: &SharedState) -> Option<Box<dyn Scene>> {
+        if self.want_title {
+            Some(Box::new(TitleScene::new()))
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Player {
+    pos: (f32, f32),
+    sprite: Rc<graphics::Image>,
+    shields: f32,
+    health: u32,
+    last_hit: Option<Instant>,
+}
+
+impl CollisionRect for Player {
+    fn top_left_x(&self) -> f32 {
+        self.pos.0 - self.sprite.width() as f32 / 2.0
+    }
+
+    fn top_left_y(&self) -> f32 {
+        self.pos.1 - self.sprite.height() as f32 / 2.0
+    }
+
+    fn width(&self) -> f32 {
+        self.sprite.width() as f32
+    }
+
+    fn height(&self) -> f32 {
+        self.sprite.height() as f32
+    }
+}
+
+impl Player {
+    const MAX_SHIELDS: f32 = 100.0;
+    const STARTING_HEALTH: u32 = 3;
+    /// How long the player has to go without being hit before shields start
+    /// regenerating again.
+    const SHIELD_DELAY: Duration = Duration::from_secs(3);
+    /// Shield points regenerated per second once `SHIELD_DELAY` has passed.
+    const SHIELD_REGEN_RATE: f32 = 20.0;
+
+    fn starting_at(pos: (f32, f32), sprite: Rc<graphics::Image>) -> Player {
+        Player {
+            pos,
+            sprite,
+            shields: Player::MAX_SHIELDS,
+            health: Player::STARTING_HEALTH,
+            last_hit: None,
+        }
+    }
+
+    fn alive(&self) -> bool {
+        self.health > 0
+    }
+
+    /// Applies a hit from a deadly bullet: it drains the shields first, and
+    /// only costs a life once they're already down.
+    fn take_hit(&mut self, now: Instant) {
+        self.last_hit = Some(now);
+        if self.shields > 0.0 {
+            self.shields = 0.0;
+        } else {
+            self.health = self.health.saturating_sub(1);
+        }
+    }
+
+    fn regenerate(&mut self, now: Instant, dt: Duration) {
+        if self.shields >= Player::MAX_SHIELDS {
+            return;
+        }
+        if let Some(last_hit) = self.last_hit {
+            if now - last_hit < Player::SHIELD_DELAY {
+                return;
+            }
+        }
+        self.shields = (self.shields + Player::SHIELD_REGEN_RATE * dt.as_secs_f32()).min(Player::MAX_SHIELDS);
+    }
+
+    fn execute_intent(&mut self, player_intent: &PlayerIntent, dt: Duration, config: &GameConfig) {
+        match *player_intent {
+            PlayerIntent::StayStill => {}
+            PlayerIntent::MoveLeft => {
+                self.pos = (
+                    self.pos.0 - config.player_speed * dt.as_secs_f32(),
+                    self.pos.1,
+                )
+            }
+            PlayerIntent::MoveRight => {
+                self.pos = (
+                    self.pos.0 + config.player_speed * dt.as_secs_f32(),
+                    self.pos.1,
+                )
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+enum PlayerIntent {
+    MoveLeft,
+    MoveRight,
+    #[default]
+    StayStill,
+}
+
+#[derive(Debug, Default)]
+struct KeysPressed {
+    left: bool,
+    right: bool,
+}
+
+pub struct GameScene {
+    fleet: AlienFleet,
+    player: Player,
+    bunkers: Vec<Bunker>,
+    bullets: Vec<Bullet>,
+    last_tick: Instant,
+    score: u32,
+    game_over: bool,
+    screen_size: (u32, u32),
+    keys_pressed: KeysPressed,
+    config: Rc<GameConfig>,
+}
+
+impl GameScene {
+    /// Cell size for the bullet `SpatialGrid` rebuilt each tick - big enough
+    /// to cover a bunker cell or the player sprite in a single bucket.
+    const COLLISION_GRID_CELL_SIZE: f32 = 32.0;
+
+    fn starting(ctx: &mut Context, shared: &SharedState) -> GameScene {
+        let canvas = Canvas::from_frame(ctx, None);
+        let screen_coordinates = canvas.screen_coordinates().unwrap();
+        let sprites = &shared.sprites;
+        let config = Rc::new(GameConfig::load(ctx));
+        GameScene {
+            fleet: AlienFleet::starting_at(
+                config.alien_fleet_start,
+                (60.0, 50.0),
+                4,
+                8,
+                (0.0, screen_coordinates.w),
+                sprites.alien_idle.clone(),
+                sprites.alien_firing.clone(),
+                config.clone(),
+            ),
+            player: Player::starting_at(config.player_start, sprites.player.clone()),
+            bunkers: (1..=4)
+                .map(|slot| {
+                    let x = screen_coordinates.w * slot as f32 / 5.0;
+                    Bunker::starting_at((x, 450.0))
+                })
+                .collect(),
+            bullets: vec![],
+            last_tick: Instant::now(),
+            score: 0,
+            game_over: false,
+            screen_size: (screen_coordinates.w as u32, screen_coordinates.h as u32),
+            keys_pressed: KeysPressed::default(),
+            config,
+        }
+    }
+
+    fn player_intent(&self) -> PlayerIntent {
+        if self.keys_pressed.left && self.keys_pressed.right {
+            PlayerIntent::StayStill
+        } else if self.keys_pressed.left {
+            PlayerIntent::MoveLeft
+        } else if self.keys_pressed.right {
+            PlayerIntent::MoveRight
+        } else {
+            PlayerIntent::StayStill
+        }
+    }
+
+    fn tick(&mut self, ctx: &mut Context, shared: &mut SharedState, dt: Duration) -> GameResult<()> {
+        let now = self.last_tick + dt;
+
+        let bullet_velocity = self.config.bullet_velocity;
+        self.bullets
+            .iter_mut()
+            .for_each(|bullet| bullet.move_down(dt, bullet_velocity));
+        if let Some(new_bullet) = self.fleet.update(
+            now,
+            dt,
+            self.player.pos,
+            &self.bullets,
+            &mut BulletFactoryImpl {
+                green_sprite: &shared.sprites.green_bullet,
+                red_sprite: &shared.sprites.red_bullet,
+            },
+        ) {
+            self.bullets.push(new_bullet);
+        }
+
+        // Broadphase every bullet once per tick instead of letting each
+        // bunker cell and the player scan the full bullet list by hand.
+        let bullet_grid = SpatialGrid::rebuild(GameScene::COLLISION_GRID_CELL_SIZE, &self.bullets);
+        let mut absorbed_bullets: HashSet<usize> = HashSet::new();
+
+        for bunker in self.bunkers.iter_mut() {
+            for (row, col, cell) in bunker.live_cells() {
+                for bullet_idx in bullet_grid.query(&cell) {
+                    if absorbed_bullets.contains(&bullet_idx) || !bunker.is_alive(row, col) {
+                        continue;
+                    }
+                    let bullet = &self.bullets[bullet_idx];
+                    if are_colliding(&cell, bullet) {
+                        bunker.hit(row, col, bullet.deadly());
+                        absorbed_bullets.insert(bullet_idx);
+                        break;
+                    }
+                }
+            }
+        }
+
+        for bullet_idx in bullet_grid.query(&self.player) {
+            if absorbed_bullets.contains(&bullet_idx) {
+                continue;
+            }
+            let bullet = &self.bullets[bullet_idx];
+            if are_colliding(&self.player, bullet) {
+                if bullet.deadly() {
+                    self.player.take_hit(now);
+                    if !self.player.alive() {
+                        self.game_over = true;
+                    }
+                } else {
+                    self.score += 1;
+                }
+                shared.audio.bloop.play(ctx)?;
+                absorbed_bullets.insert(bullet_idx);
+            }
+        }
+
+        let mut idx = 0;
+        self.bullets.retain(|_| {
+            let keep = !absorbed_bullets.contains(&idx);
+            idx += 1;
+            keep
+        });
+
+        self.player.regenerate(now, dt);
+        self.player
+            .execute_intent(&self.player_intent(), dt, &self.config);
+        clean_up_out_of_bounds_bullets(self);
+
+        self.last_tick = now;
+        Ok(())
+    }
+}
+
+fn distance(p1: &(f32, f32), p2: &(f32, f32)) -> f32 {
+    ((p1.0 - p2.0).powi(2) + (p1.1 - p2.1).powi(2)).sqrt()
+}
+
+fn clean_up_out_of_bounds_bullets(game: &mut GameScene) {
+    let screen_area = (game.screen_size.0 * game.screen_size.1) as f32;
+    game.bullets.retain(|bullet| {
+        // Delete them once they are very far away.
+        // TODO: Replace with more sensitive checking for score keeping.
+        distance(&bullet.pos(), &(0.0, 0.0)) < screen_area
+    });
+}
+
+fn draw_background(canvas: &mut Canvas, shared: &SharedState) {
+    shared.sprites.background.draw(canvas, DrawParam::default())
+}
+
+fn draw_bullets(canvas: &mut Canvas, game: &GameScene) {
+    for bullet in game.bullets.iter() {
+        bullet.draw(canvas);
+    }
+}
+
+fn draw_enemy(canvas: &mut Canvas, game: &GameScene) {
+    game.fleet.draw(canvas)
+}
+
+fn draw_bunkers(ctx: &mut Context, canvas: &mut Canvas, game: &GameScene) -> GameResult<()> {
+    for bunker in game.bunkers.iter() {
+        bunker.draw(ctx, canvas)?;
+    }
+    Ok(())
+}
+
+fn draw_player(canvas: &mut Canvas, game: &GameScene, shared: &SharedState) {
+    shared.sprites.player.draw(
+        canvas,
+        DrawParam::default()
+            .offset(Point2 { x: 0.5, y: 0.5 })
+            .dest(Point2 {
+                x: game.player.pos.0,
+                y: game.player.pos.1,
+            })
+            .rotation(FRAC_PI_2),
+    );
+}
+
+fn draw_player_status(ctx: &mut Context, canvas: &mut Canvas, game: &GameScene) -> GameResult<()> {
+    const BAR_WIDTH: f32 = 60.0;
+    const BAR_HEIGHT: f32 = 6.0;
+
+    let x = game.player.pos.0 - BAR_WIDTH / 2.0;
+    let y = game.player.pos.1 + 30.0;
+
+    let shield_pct = (game.player.shields / Player::MAX_SHIELDS).clamp(0.0, 1.0);
+    if shield_pct > 0.0 {
+        let shield_rect = graphics::Rect::new(x, y, BAR_WIDTH * shield_pct, BAR_HEIGHT);
+        let shield_mesh = Mesh::new_rectangle(ctx, DrawMode::fill(), shield_rect, Color::CYAN)?;
+        shield_mesh.draw(canvas, DrawParam::default());
+    }
+
+    let health_pct = game.player.health as f32 / Player::STARTING_HEALTH as f32;
+    if health_pct > 0.0 {
+        let health_rect = graphics::Rect::new(x, y + BAR_HEIGHT + 2.0, BAR_WIDTH * health_pct, BAR_HEIGHT);
+        let health_mesh = Mesh::new_rectangle(ctx, DrawMode::fill(), health_rect, Color::RED)?;
+        health_mesh.draw(canvas, DrawParam::default());
+    }
+
+    Ok(())
+}
+
+fn draw_score(canvas: &mut Canvas, game: &GameScene) {
+    let text = Text::new(format!("{}", game.score));
+    text.draw(
+        canvas,
+        DrawParam::default().dest(Point2 {
+            x: game.screen_size.0 as f32 / 2.0,
+            y: game.screen_size.1 as f32 / 2.0,
+        }),
+    );
+}
+
+impl Scene for GameScene {
+    fn update(&mut self, ctx: &mut Context, shared: &mut SharedState) -> GameResult<()> {
+        let dt = Instant::now() - self.last_tick;
+        self.tick(ctx, shared, dt)
+    }
+
+    fn draw(&mut self, ctx: &mut Context, shared: &SharedState, canvas: &mut Canvas) -> GameResult<()> {
+        draw_background(canvas, shared);
+        draw_bullets(canvas, self);
+        draw_enemy(canvas, self);
+        draw_bunkers(ctx, canvas, self)?;
+        draw_player(canvas, self, shared);
+        draw_player_status(ctx, canvas, self)?;
+        draw_score(canvas, self);
+        Ok(())
+    }
+
+    fn key_down(&mut self, ctx: &mut Context, keycode: KeyCode) {
+        match keycode {
+            KeyCode::Escape => ctx.request_quit(),
+            KeyCode::Left => self.keys_pressed.left = true,
+            KeyCode::Right => self.keys_pressed.right = true,
+            _ => (),
+        }
+    }
+
+    fn key_up(&mut self, _ctx: &mut Context, keycode: KeyCode) {
+        match keycode {
+            KeyCode::Left => self.keys_pressed.left = false,
+            KeyCode::Right => self.keys_pressed.right = false,
+            _ => (),
+        }
+    }
+
+    fn resize(&mut self, width: f32, height: f32) {
+        self.screen_size = (width as u32, height as u32);
+    }
+
+    fn next_scene(&mut self, _ctx: &mut Context, _shared: &SharedState) -> Option<Box<dyn Scene>> {
+        if self.game_over {
+            Some(Box::new(GameOverScene::new(self.score)))
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use crate::simple_collision::CollisionRect;
+use ggez::graphics::{Canvas, Color, DrawMode, Drawable, Mesh};
+use ggez::{graphics, Context, GameResult};
+
+/// A single destructible cell of a `Bunker`, used to satisfy `CollisionRect`
+/// for narrowphase checks against incoming bullets. Callers get these from
+/// `Bunker::live_cells` to broadphase against with a `SpatialGrid` instead
+/// of scanning every cell by hand.
+#[derive(Copy, Clone)]
+pub(crate) struct Cell {
+    top_left: (f32, f32),
+    size: f32,
+}
+
+impl CollisionRect for Cell {
+    fn top_left_x(&self) -> f32 {
+        self.top_left.0
+    }
+
+    fn top_left_y(&self) -> f32 {
+        self.top_left.1
+    }
+
+    fn width(&self) -> f32 {
+        self.size
+    }
+
+    fn height(&self) -> f32 {
+        self.size
+    }
+}
+
+/// A destructible shield the player can hide behind, modeled as a grid of
+/// cells that erode as bullets hit them.
+pub struct Bunker {
+    pos: (f32, f32),
+    cells: Vec<Vec<bool>>,
+}
+
+impl Bunker {
+    const ROWS: usize = 4;
+    const COLS: usize = 8;
+    const CELL_SIZE: f32 = 8.0;
+
+    pub fn starting_at(pos: (f32, f32)) -> Bunker {
+        Bunker {
+            pos,
+            cells: vec![vec![true; Bunker::COLS]; Bunker::ROWS],
+        }
+    }
+
+    fn cell(&self, row: usize, col: usize) -> Cell {
+        Cell {
+            top_left: (
+                self.pos.0 + col as f32 * Bunker::CELL_SIZE,
+                self.pos.1 + row as f32 * Bunker::CELL_SIZE,
+            ),
+            size: Bunker::CELL_SIZE,
+        }
+    }
+
+    /// Clears every in-bounds cell around `(row, col)` - all eight neighbors,
+    /// the blast radius of a deadly bullet.
+    fn damage_neighbors(&mut self, row: usize, col: usize) {
+        for d_row in -1i32..=1 {
+            for d_col in -1i32..=1 {
+                if d_row == 0 && d_col == 0 {
+                    continue;
+                }
+                let row = row as i32 + d_row;
+                let col = col as i32 + d_col;
+                if row >= 0 && (row as usize) < Bunker::ROWS && col >= 0 && (col as usize) < Bunker::COLS {
+                    self.cells[row as usize][col as usize] = false;
+                }
+            }
+        }
+    }
+
+    /// Every live cell, for broadphasing against incoming bullets with a
+    /// `SpatialGrid` instead of checking all of them by hand.
+    pub(crate) fn live_cells(&self) -> Vec<(usize, usize, Cell)> {
+        let mut cells = Vec::new();
+        for row in 0..Bunker::ROWS {
+            for col in 0..Bunker::COLS {
+                if self.cells[row][col] {
+                    cells.push((row, col, self.cell(row, col)));
+                }
+            }
+        }
+        cells
+    }
+
+    /// Whether `(row, col)` is still standing - callers broadphasing off of
+    /// a `live_cells()` snapshot need to recheck this before trusting a
+    /// match, since an earlier hit in the same pass may have taken the cell
+    /// out via `damage_neighbors`.
+    pub(crate) fn is_alive(&self, row: usize, col: usize) -> bool {
+        self.cells[row][col]
+    }
+
+    /// Clears `(row, col)` (and, for a deadly bullet, a small blast radius
+    /// of neighbors), once the caller has confirmed a bullet hit it.
+    pub(crate) fn hit(&mut self, row: usize, col: usize, deadly: bool) {
+        self.cells[row][col] = false;
+        if deadly {
+            self.damage_neighbors(row, col);
+        }
+    }
+
+    pub fn draw(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult<()> {
+        for row in 0..Bunker::ROWS {
+            for col in 0..Bunker::COLS {
+                if !self.cells[row][col] {
+                    continue;
+                }
+                let cell = self.cell(row, col);
+                let mesh = Mesh::new_rectangle(
+                    ctx,
+                    DrawMode::fill(),
+                    graphics::Rect::new(cell.top_left_x(), cell.top_left_y(), cell.width(), cell.height()),
+                    Color::GREEN,
+                )?;
+                mesh.draw(canvas, graphics::DrawParam::default());
+            }
+        }
+        Ok(())
+    }
+}
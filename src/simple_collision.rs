@@ -1,3 +1,5 @@
+use std::collections::{HashMap, HashSet};
+
 /// Rectangle for checking collisions
 pub trait CollisionRect {
     fn top_left_x(&self) -> f32;
@@ -16,3 +18,150 @@ pub fn are_colliding<Rect1: CollisionRect, Rect2: CollisionRect>(
         && rect1.top_left_y() < rect2.top_left_y() + rect2.height()
         && rect1.top_left_y() + rect1.height() > rect2.top_left_y()
 }
+
+/// A uniform-grid broadphase: buckets entities into fixed-size cells by the
+/// span of their top-left/bottom-right corners, so narrowphase `are_colliding`
+/// checks only need to run on the handful of entities sharing a cell instead
+/// of every pair.
+pub struct SpatialGrid {
+    cell_size: f32,
+    cells: HashMap<(i64, i64), Vec<usize>>,
+}
+
+impl SpatialGrid {
+    /// Buckets every entity's span of cells, keyed by entity index into
+    /// `entities`. Call this once per frame before querying.
+    pub fn rebuild<Rect: CollisionRect>(cell_size: f32, entities: &[Rect]) -> SpatialGrid {
+        let mut cells: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+        for (idx, entity) in entities.iter().enumerate() {
+            for cell in SpatialGrid::cells_for(cell_size, entity) {
+                cells.entry(cell).or_insert_with(Vec::new).push(idx);
+            }
+        }
+        SpatialGrid { cell_size, cells }
+    }
+
+    fn cells_for(cell_size: f32, rect: &impl CollisionRect) -> impl Iterator<Item = (i64, i64)> {
+        let min_x = (rect.top_left_x() / cell_size).floor() as i64;
+        let min_y = (rect.top_left_y() / cell_size).floor() as i64;
+        let max_x = ((rect.top_left_x() + rect.width()) / cell_size).floor() as i64;
+        let max_y = ((rect.top_left_y() + rect.height()) / cell_size).floor() as i64;
+        (min_x..=max_x).flat_map(move |x| (min_y..=max_y).map(move |y| (x, y)))
+    }
+
+    /// Indices into the `entities` passed to `rebuild` that share at least
+    /// one cell with `rect` - a superset of what's actually colliding, meant
+    /// to be confirmed with `are_colliding`.
+    pub fn query(&self, rect: &impl CollisionRect) -> Vec<usize> {
+        let mut found = HashSet::new();
+        for cell in SpatialGrid::cells_for(self.cell_size, rect) {
+            if let Some(indices) = self.cells.get(&cell) {
+                found.extend(indices.iter().copied());
+            }
+        }
+        found.into_iter().collect()
+    }
+
+    /// Every unordered pair of entity indices that share a cell - candidates
+    /// for a narrowphase `are_colliding` confirm.
+    pub fn potential_pairs(&self) -> HashSet<(usize, usize)> {
+        let mut pairs = HashSet::new();
+        for indices in self.cells.values() {
+            for i in 0..indices.len() {
+                for j in (i + 1)..indices.len() {
+                    let (a, b) = (indices[i], indices[j]);
+                    pairs.insert(if a < b { (a, b) } else { (b, a) });
+                }
+            }
+        }
+        pairs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    struct TestRect {
+        top_left: (f32, f32),
+        size: (f32, f32),
+    }
+
+    impl CollisionRect for TestRect {
+        fn top_left_x(&self) -> f32 {
+            self.top_left.0
+        }
+
+        fn top_left_y(&self) -> f32 {
+            self.top_left.1
+        }
+
+        fn width(&self) -> f32 {
+            self.size.0
+        }
+
+        fn height(&self) -> f32 {
+            self.size.1
+        }
+    }
+
+    fn brute_force_pairs(entities: &[TestRect]) -> HashSet<(usize, usize)> {
+        let mut pairs = HashSet::new();
+        for i in 0..entities.len() {
+            for j in (i + 1)..entities.len() {
+                if are_colliding(&entities[i], &entities[j]) {
+                    pairs.insert((i, j));
+                }
+            }
+        }
+        pairs
+    }
+
+    fn grid_confirmed_pairs(entities: &[TestRect], cell_size: f32) -> HashSet<(usize, usize)> {
+        let grid = SpatialGrid::rebuild(cell_size, entities);
+        grid.potential_pairs()
+            .into_iter()
+            .filter(|&(a, b)| are_colliding(&entities[a], &entities[b]))
+            .collect()
+    }
+
+    #[test]
+    fn matches_brute_force_on_random_layouts() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..50 {
+            let entities: Vec<TestRect> = (0..100)
+                .map(|_| TestRect {
+                    top_left: (rng.gen_range(0.0, 500.0), rng.gen_range(0.0, 500.0)),
+                    size: (rng.gen_range(1.0, 40.0), rng.gen_range(1.0, 40.0)),
+                })
+                .collect();
+
+            assert_eq!(grid_confirmed_pairs(&entities, 32.0), brute_force_pairs(&entities));
+        }
+    }
+
+    #[test]
+    fn query_contains_every_colliding_entity() {
+        let mut rng = rand::thread_rng();
+        let entities: Vec<TestRect> = (0..100)
+            .map(|_| TestRect {
+                top_left: (rng.gen_range(0.0, 500.0), rng.gen_range(0.0, 500.0)),
+                size: (rng.gen_range(1.0, 40.0), rng.gen_range(1.0, 40.0)),
+            })
+            .collect();
+        let probe = TestRect {
+            top_left: (250.0, 250.0),
+            size: (20.0, 20.0),
+        };
+
+        let grid = SpatialGrid::rebuild(32.0, &entities);
+        let candidates: HashSet<usize> = grid.query(&probe).into_iter().collect();
+
+        for (idx, entity) in entities.iter().enumerate() {
+            if are_colliding(&probe, entity) {
+                assert!(candidates.contains(&idx));
+            }
+        }
+    }
+}